@@ -7,6 +7,7 @@ use std::collections::HashMap;
 use std::fmt::Display;
 use std::fs;
 use std::io::Error as IOError;
+use std::path::Path;
 use toml::de::Error as TomlError;
 
 #[derive(Default, Debug, Clone, PartialEq)]
@@ -24,13 +25,133 @@ pub struct ConfigRaw {
     pub icons: Option<String>,
     pub keys: Option<KeymapConfig>,
     pub editor: Option<toml::Value>,
+    /// Whether a workspace (`.helix/config.toml`) config is allowed to be loaded at all.
+    /// Only ever honored when read from the *global* config; a workspace config setting
+    /// this on itself has no effect on whether it gets trusted.
+    #[serde(default, rename = "load-workspace-config")]
+    pub load_workspace_config: WorkspaceConfigMode,
+    /// Additional `editor.*` keys to strip from a workspace config's `editor` table on
+    /// top of the built-in denylist (`shell`, anything ending in `-command`). Only ever
+    /// honored when read from the *global* config, same as `load_workspace_config`.
+    #[serde(default, rename = "sensitive-editor-keys")]
+    pub sensitive_editor_keys: Vec<String>,
+}
+
+/// Controls whether a workspace-local `.helix/config.toml` is trusted enough to be
+/// merged into the running config. Defaults to `Never` so that cloning an untrusted
+/// repository can't silently escalate to running arbitrary shell commands via
+/// `editor.shell` or a formatter/command hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WorkspaceConfigMode {
+    /// Never load workspace configs.
+    Never,
+    /// Always load workspace configs (sensitive keys are still stripped).
+    Always,
+    /// Ask the user before loading a workspace config for the first time.
+    ///
+    /// Resolving the prompt is a UI concern; at the config-loading layer this behaves
+    /// the same as `Never` and surfaces `ConfigLoadError::UntrustedWorkspaceConfig` so
+    /// the caller can prompt and retry with `Always`.
+    Prompt,
+}
+
+impl Default for WorkspaceConfigMode {
+    fn default() -> Self {
+        WorkspaceConfigMode::Never
+    }
+}
+
+/// Config keys that must never be allowed to cross from a workspace config into the
+/// running editor, since they can be used to execute arbitrary commands.
+fn is_sensitive_editor_key(key: &str, extra_denylist: &[String]) -> bool {
+    key == "shell" || key.ends_with("-command") || extra_denylist.iter().any(|k| k == key)
+}
+
+/// Strips sensitive keys (see [`is_sensitive_editor_key`]) from a trusted workspace
+/// config's `editor` table before it is merged into the rest of the config.
+/// `extra_denylist` comes from the global config's `sensitive-editor-keys`.
+fn strip_sensitive_editor_keys(mut editor: toml::Value, extra_denylist: &[String]) -> toml::Value {
+    if let toml::Value::Table(table) = &mut editor {
+        table.retain(|key, _| !is_sensitive_editor_key(key, extra_denylist));
+    }
+    editor
+}
+
+/// A predicate guarding a [`SuperTabRule`]. The first rule in a
+/// [`KeymapConfig::supertab`] list whose predicate matches wins; if none match, Tab
+/// inserts normally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SuperTabCondition {
+    /// The completion popup is open.
+    CompletionVisible,
+    /// Signature help is open.
+    SignatureHelpVisible,
+    /// The cursor is inside an active snippet's tabstops.
+    InSnippet,
+    /// There is non-whitespace text to the left of the cursor on the current line.
+    NonWhitespaceLeft,
+    /// Always matches; useful as a final catch-all rule.
+    Always,
+}
+
+/// Either a single command to run, or a nested keymap to fall back to, when a
+/// [`SuperTabRule`]'s predicate matches.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum SuperTabBranch {
+    Command(MappableCommand),
+    KeyTrie(Box<KeyTrie>),
+}
+
+/// One guarded entry in a [`KeymapConfig::supertab`] list: run `command` when `when`
+/// matches.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SuperTabRule {
+    pub when: SuperTabCondition,
+    pub command: SuperTabBranch,
+}
+
+/// Accepts either the legacy scalar form (a single command, implicitly guarded by
+/// `non-whitespace-left` to match the historical supertab behavior) or the new
+/// array-of-tables form.
+fn deserialize_supertab<'de, D>(deserializer: D) -> Result<Option<Vec<SuperTabRule>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        Legacy(MappableCommand),
+        Rules(Vec<SuperTabRule>),
+    }
+
+    Ok(Some(match Repr::deserialize(deserializer)? {
+        Repr::Legacy(command) => vec![SuperTabRule {
+            when: SuperTabCondition::NonWhitespaceLeft,
+            command: SuperTabBranch::Command(command),
+        }],
+        Repr::Rules(rules) => rules,
+    }))
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct KeymapConfig {
-    /// An alternative command to run when tab is pressed and the cursor has
-    /// text other than whitespace to its left on the current line.
-    pub supertab: Option<MappableCommand>,
+    /// An ordered list of guarded commands to run when tab is pressed, e.g. to cycle
+    /// completion when the popup is open, jump through a snippet's tabstops, or fall
+    /// back to a plain command otherwise. The first matching rule wins; a local
+    /// config's rules replace the global list wholesale rather than concatenating.
+    #[serde(default, deserialize_with = "deserialize_supertab")]
+    pub supertab: Option<Vec<SuperTabRule>>,
+
+    /// Discard every binding accumulated so far (the built-in defaults, and any
+    /// bindings from less-specific config layers) before merging in this layer's
+    /// `bindings`, for users who want a fully custom modal layout. Setting this in a
+    /// workspace config clears the global config's custom bindings too, not just the
+    /// built-in keymap, since "a clean base" means exactly that for the layer that asks.
+    #[serde(default, rename = "unbind-default-keys")]
+    pub unbind_default_keys: bool,
 
     #[serde(flatten)]
     pub bindings: HashMap<Mode, KeyTrie>,
@@ -40,6 +161,7 @@ impl Default for KeymapConfig {
     fn default() -> KeymapConfig {
         KeymapConfig {
             supertab: None,
+            unbind_default_keys: false,
             bindings: keymap::default(),
         }
     }
@@ -49,6 +171,12 @@ impl Default for KeymapConfig {
 pub enum ConfigLoadError {
     BadConfig(TomlError),
     Error(IOError),
+    /// A workspace config was found but the global config's trust policy (see
+    /// [`WorkspaceConfigMode`]) didn't allow loading it outright. The workspace config
+    /// was ignored in its entirety. Carries the resolved mode so the caller can tell
+    /// `Never` (silently ignore) apart from `Prompt` (ask the user, then retry with
+    /// `load-workspace-config = "always"`) without re-reading the global config itself.
+    UntrustedWorkspaceConfig(WorkspaceConfigMode),
 }
 
 impl Default for ConfigLoadError {
@@ -62,102 +190,329 @@ impl Display for ConfigLoadError {
         match self {
             ConfigLoadError::BadConfig(err) => err.fmt(f),
             ConfigLoadError::Error(err) => err.fmt(f),
+            ConfigLoadError::UntrustedWorkspaceConfig(WorkspaceConfigMode::Prompt) => f.write_str(
+                "a workspace config was found: approve it by adding \
+                 `load-workspace-config = \"always\"` to your global config to trust \
+                 workspace configs in this and other projects",
+            ),
+            ConfigLoadError::UntrustedWorkspaceConfig(_) => f.write_str(
+                "ignored untrusted workspace config: add `load-workspace-config = \"always\"` \
+                 to your global config to trust workspace configs in this and other projects",
+            ),
+        }
+    }
+}
+
+impl Default for ConfigRaw {
+    fn default() -> Self {
+        ConfigRaw {
+            theme: None,
+            icons: None,
+            keys: None,
+            editor: None,
+            load_workspace_config: WorkspaceConfigMode::default(),
+            sensitive_editor_keys: Vec::new(),
         }
     }
 }
 
+impl ConfigRaw {
+    /// Merges a workspace `local` config onto a `global` one, following the same
+    /// precedence rules `Config::load` always has: `theme`/`icons` take the
+    /// last-defined value, `keys` merge per-mode binding tries (honoring
+    /// `unbind-default-keys`), and `editor` merges as TOML tables three levels deep.
+    fn merge(global: ConfigRaw, local: ConfigRaw) -> ConfigRaw {
+        let keys = match (global.keys, local.keys) {
+            (None, None) => None,
+            (None, Some(local)) => Some(local),
+            (Some(global), None) => Some(global),
+            (Some(global), Some(local)) => {
+                let mut bindings = if local.unbind_default_keys {
+                    HashMap::new()
+                } else {
+                    global.bindings
+                };
+                merge_keys(&mut bindings, local.bindings);
+
+                Some(KeymapConfig {
+                    supertab: local.supertab.or(global.supertab),
+                    unbind_default_keys: global.unbind_default_keys || local.unbind_default_keys,
+                    bindings,
+                })
+            }
+        };
+
+        let editor = match (global.editor, local.editor) {
+            (None, None) => None,
+            (None, Some(val)) | (Some(val), None) => Some(val),
+            (Some(global), Some(local)) => Some(merge_toml_values(global, local, 3)),
+        };
+
+        ConfigRaw {
+            theme: local.theme.or(global.theme),
+            icons: local.icons.or(global.icons),
+            keys,
+            editor,
+            load_workspace_config: global.load_workspace_config,
+            sensitive_editor_keys: global.sensitive_editor_keys,
+        }
+    }
+}
+
+/// Finds the root of the current workspace by walking up from `cwd` looking for a
+/// `.git` entry (a directory for a normal checkout, a file for a worktree), the same
+/// boundary a VCS-aware tool would use to tell "this project" apart from its
+/// surrounding filesystem. Falls back to `cwd` itself if no such boundary is found, so
+/// a directory outside of any repository still gets exactly its own config
+/// considered, not every directory up to `/`.
+fn find_workspace_root(cwd: &Path) -> &Path {
+    cwd.ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .unwrap_or(cwd)
+}
+
+/// Walks `cwd` up to the workspace root (see [`find_workspace_root`]), collecting the
+/// contents of every `.helix/config.toml` found along the way, ordered from the
+/// workspace root to `cwd` itself (so the layer closest to `cwd` is last, and wins in
+/// `load_cascade`). The walk never crosses the workspace boundary, so an unrelated
+/// `.helix/config.toml` higher up the filesystem (e.g. in `$HOME` or `/`) is never
+/// picked up as a workspace layer.
+fn discover_workspace_configs(cwd: &Path) -> Vec<Result<String, ConfigLoadError>> {
+    let root = find_workspace_root(cwd);
+
+    cwd.ancestors()
+        .take_while(|dir| dir.starts_with(root))
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .filter_map(|dir| {
+            let candidate = dir.join(".helix").join("config.toml");
+            candidate
+                .is_file()
+                .then(|| fs::read_to_string(candidate).map_err(ConfigLoadError::Error))
+        })
+        .collect()
+}
+
 impl Config {
     pub fn load(
         global: Result<String, ConfigLoadError>,
         local: Result<String, ConfigLoadError>,
-    ) -> Result<Config, ConfigLoadError> {
+    ) -> Result<(Config, Vec<ConfigLoadError>), ConfigLoadError> {
+        Self::load_cascade(global, vec![local])
+    }
+
+    /// Loads a global config plus an ordered list of workspace config layers, folding
+    /// them with the same precedence rules `load` uses for its single workspace layer.
+    /// `locals` is expected to be ordered from the outermost workspace root down to the
+    /// current directory, as produced by [`discover_workspace_configs`]; whichever layer
+    /// is closest to the file being edited should come last, since the closer layer
+    /// wins.
+    ///
+    /// The global config's trust policy (see [`WorkspaceConfigMode`]) gates every layer
+    /// in `locals`, not just the nearest one. An untrusted workspace layer is never
+    /// fatal: it's dropped, folding stops there (every remaining layer would be
+    /// rejected by the same policy anyway), and the `Config` built from whatever was
+    /// validly merged so far — the global config's own settings included — is still
+    /// returned. The rejection itself comes back alongside it as a warning, so a
+    /// caller can surface it (e.g. `Prompt` asking the user to retry with `Always`)
+    /// without that surfacing ever costing the user their global config.
+    pub fn load_cascade(
+        global: Result<String, ConfigLoadError>,
+        locals: Vec<Result<String, ConfigLoadError>>,
+    ) -> Result<(Config, Vec<ConfigLoadError>), ConfigLoadError> {
         let global_config: Result<ConfigRaw, ConfigLoadError> =
             global.and_then(|file| toml::from_str(&file).map_err(ConfigLoadError::BadConfig));
 
-        let local_config: Result<ConfigRaw, ConfigLoadError> =
-            local.and_then(|file| toml::from_str(&file).map_err(ConfigLoadError::BadConfig));
-
-        let mut result_keymap_config = KeymapConfig::default();
-
-        let mut merge_keymap_configs = |config: &ConfigRaw| {
-            let result_keymap_config = &mut result_keymap_config;
+        let mode = global_config
+            .as_ref()
+            .map(|global| global.load_workspace_config)
+            .unwrap_or_default();
 
-            if let Some(ref keymap_config) = config.keys {
-                if let Some(supertab_config) = &keymap_config.supertab {
-                    result_keymap_config.supertab = Some(supertab_config.clone());
-                }
+        let sensitive_editor_keys: Vec<String> = global_config
+            .as_ref()
+            .map(|global| global.sensitive_editor_keys.clone())
+            .unwrap_or_default();
 
-                merge_keys(
-                    &mut result_keymap_config.bindings,
-                    keymap_config.bindings.clone(),
-                )
+        // A missing global config isn't fatal on its own; only remember the error in
+        // case no layer ends up contributing anything, to preserve `load`'s historical
+        // behavior of surfacing it when there's truly nothing to load.
+        let mut fallback_err = None;
+        let mut merged = match global_config {
+            Ok(global) => global,
+            Err(ConfigLoadError::BadConfig(err)) => return Err(ConfigLoadError::BadConfig(err)),
+            Err(err) => {
+                fallback_err = Some(err);
+                ConfigRaw::default()
             }
         };
 
-        let res = match (global_config, local_config) {
-            (Ok(global), Ok(local)) => {
-                merge_keymap_configs(&global);
-                merge_keymap_configs(&local);
-
-                let editor = match (global.editor, local.editor) {
-                    (None, None) => helix_view::editor::Config::default(),
-                    (None, Some(val)) | (Some(val), None) => {
-                        val.try_into().map_err(ConfigLoadError::BadConfig)?
-                    }
-                    (Some(global), Some(local)) => merge_toml_values(global, local, 3)
-                        .try_into()
-                        .map_err(ConfigLoadError::BadConfig)?,
-                };
+        let mut any_local_loaded = false;
+        let mut warnings = Vec::new();
 
-                Config {
-                    theme: local.theme.or(global.theme),
-                    icons: local.icons.or(global.icons),
-                    keys: result_keymap_config,
-                    editor,
+        for local in locals {
+            // A layer that couldn't be read was never found, so there is no workspace
+            // config to trust or reject.
+            let file = match local {
+                Ok(file) => file,
+                Err(ConfigLoadError::BadConfig(err)) => {
+                    return Err(ConfigLoadError::BadConfig(err))
                 }
+                Err(_) => continue,
+            };
+
+            // A workspace config is only ever applied if the global config opts in;
+            // otherwise it's rejected wholesale rather than silently ignored, so the
+            // caller can tell the user why their `.helix/config.toml` had no effect.
+            // `mode` can't change mid-cascade, so once one layer is rejected every
+            // later one would be too; stop folding rather than parsing layers whose
+            // content can no longer matter.
+            if mode != WorkspaceConfigMode::Always {
+                warnings.push(ConfigLoadError::UntrustedWorkspaceConfig(mode));
+                break;
             }
-            // if any configs are invalid return that first
-            (_, Err(ConfigLoadError::BadConfig(err)))
-            | (Err(ConfigLoadError::BadConfig(err)), _) => {
-                return Err(ConfigLoadError::BadConfig(err))
-            }
-            (Ok(config), Err(_)) | (Err(_), Ok(config)) => {
-                merge_keymap_configs(&config);
-
-                Config {
-                    theme: config.theme,
-                    icons: config.icons,
-                    keys: result_keymap_config,
-                    editor: config.editor.map_or_else(
-                        || Ok(helix_view::editor::Config::default()),
-                        |val| val.try_into().map_err(ConfigLoadError::BadConfig),
-                    )?,
-                }
+
+            let mut local_config: ConfigRaw =
+                toml::from_str(&file).map_err(ConfigLoadError::BadConfig)?;
+
+            if let Some(editor) = local_config.editor.take() {
+                local_config.editor =
+                    Some(strip_sensitive_editor_keys(editor, &sensitive_editor_keys));
             }
 
-            // these are just two io errors return the one for the global config
-            (Err(err), Err(_)) => return Err(err),
-        };
+            merged = ConfigRaw::merge(merged, local_config);
+            any_local_loaded = true;
+        }
+
+        if !any_local_loaded && warnings.is_empty() {
+            if let Some(err) = fallback_err {
+                return Err(err);
+            }
+        }
 
-        Ok(res)
+        Self::resolve(merged).map(|config| (config, warnings))
     }
 
-    pub fn load_default() -> Result<Config, ConfigLoadError> {
+    pub fn load_default() -> Result<(Config, Vec<ConfigLoadError>), ConfigLoadError> {
         let global_config =
             fs::read_to_string(helix_loader::config_file()).map_err(ConfigLoadError::Error);
-        let local_config = fs::read_to_string(helix_loader::workspace_config_file())
-            .map_err(ConfigLoadError::Error);
-        Config::load(global_config, local_config)
+
+        let locals = match std::env::current_dir() {
+            Ok(cwd) => discover_workspace_configs(&cwd),
+            Err(err) => vec![Err(ConfigLoadError::Error(err))],
+        };
+
+        Config::load_cascade(global_config, locals)
+    }
+
+    /// Re-reads the global and workspace config from disk, the same way `load_default`
+    /// does at startup. Meant for a runtime reload trigger (e.g. a `USR1` signal
+    /// handler); pair it with `diff` to apply only what actually changed.
+    pub fn reload_default() -> Result<(Config, Vec<ConfigLoadError>), ConfigLoadError> {
+        Config::load_default()
+    }
+
+    /// Builds a [`Config`] from an already-merged [`ConfigRaw`], filling in defaults
+    /// (the built-in keymap, unless `unbind-default-keys` was set, and the default
+    /// editor config) for anything the raw config left unspecified.
+    fn resolve(raw: ConfigRaw) -> Result<Config, ConfigLoadError> {
+        let mut bindings = match &raw.keys {
+            Some(keys) if keys.unbind_default_keys => HashMap::new(),
+            _ => keymap::default(),
+        };
+
+        let mut supertab = None;
+        if let Some(keys) = raw.keys {
+            supertab = keys.supertab;
+            merge_keys(&mut bindings, keys.bindings);
+        }
+
+        let editor = raw.editor.map_or_else(
+            || Ok(helix_view::editor::Config::default()),
+            |val| val.try_into().map_err(ConfigLoadError::BadConfig),
+        )?;
+
+        Ok(Config {
+            theme: raw.theme,
+            icons: raw.icons,
+            keys: KeymapConfig {
+                supertab,
+                unbind_default_keys: false,
+                bindings,
+            },
+            editor,
+        })
+    }
+
+    /// Compares `self` against a freshly loaded `new` config and reports which
+    /// top-level pieces actually changed, so a reload handler can apply only what
+    /// moved (rebind keymaps, swap the theme) instead of reconstructing the whole
+    /// editor.
+    pub fn diff(&self, new: &Config) -> ConfigChanges {
+        ConfigChanges {
+            theme: self.theme != new.theme,
+            icons: self.icons != new.icons,
+            keys: self.keys.supertab != new.keys.supertab
+                || self.keys.bindings != new.keys.bindings,
+            editor: editor_field_diff(&self.editor, &new.editor),
+        }
     }
 }
 
+/// A coarse-grained report of what changed between two [`Config`]s, as produced by
+/// [`Config::diff`]. `editor` lists the individual fields of `editor::Config` that
+/// differ, since which ones changed often determines whether a reload needs to touch
+/// LSP clients, re-render, or do nothing at all.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigChanges {
+    pub theme: bool,
+    pub icons: bool,
+    pub keys: bool,
+    pub editor: Vec<String>,
+}
+
+impl ConfigChanges {
+    pub fn is_empty(&self) -> bool {
+        !self.theme && !self.icons && !self.keys && self.editor.is_empty()
+    }
+}
+
+/// Diffs two already-parsed editor configs by serializing each to a TOML table and
+/// comparing keys, rather than diffing raw TOML, so that defaults filled in by serde
+/// don't spuriously show up as changes. Falls back to reporting no changes if either
+/// side doesn't round-trip through TOML at all, which isn't reachable through any
+/// `editor::Config` this module can construct but is cheap insurance against a future
+/// field that doesn't serialize the way TOML expects.
+fn editor_field_diff(
+    old: &helix_view::editor::Config,
+    new: &helix_view::editor::Config,
+) -> Vec<String> {
+    let (Ok(toml::Value::Table(old)), Ok(toml::Value::Table(new))) =
+        (toml::Value::try_from(old), toml::Value::try_from(new))
+    else {
+        return Vec::new();
+    };
+
+    let mut changed: Vec<String> = old
+        .keys()
+        .chain(new.keys())
+        .filter(|key| old.get(*key) != new.get(*key))
+        .cloned()
+        .collect();
+    changed.sort_unstable();
+    changed.dedup();
+    changed
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     impl Config {
         fn load_test(config: &str) -> Config {
-            Config::load(Ok(config.to_owned()), Err(ConfigLoadError::default())).unwrap()
+            Config::load(Ok(config.to_owned()), Err(ConfigLoadError::default()))
+                .unwrap()
+                .0
         }
     }
 
@@ -210,4 +565,336 @@ mod tests {
         let default_keys = Config::default().keys.bindings;
         assert_eq!(default_keys, keymap::default());
     }
+
+    #[test]
+    fn untrusted_workspace_config_is_ignored_not_fatal() {
+        let global = r#"theme = "global-theme""#;
+        let local = r#"
+            theme = "workspace-theme"
+
+            [editor]
+            shell = ["sh", "-c"]
+        "#;
+
+        // An untrusted workspace config must never make config loading fail
+        // outright: the global config's own settings still have to come through,
+        // with the rejection surfaced as a warning alongside them rather than as
+        // the `Result`'s `Err` arm.
+        let (config, warnings) =
+            Config::load(Ok(global.to_owned()), Ok(local.to_owned())).unwrap();
+
+        assert_eq!(config.theme.as_deref(), Some("global-theme"));
+        assert_eq!(warnings.len(), 1);
+        assert!(matches!(
+            warnings[0],
+            ConfigLoadError::UntrustedWorkspaceConfig(WorkspaceConfigMode::Never)
+        ));
+    }
+
+    #[test]
+    fn missing_global_config_with_untrusted_workspace_still_resolves() {
+        // No global config on disk yet defaults `load-workspace-config` to `Never`,
+        // same as an explicit `Never`; that combination must still resolve to a
+        // usable (default) `Config` instead of failing outright.
+        let (config, warnings) =
+            Config::load(Err(ConfigLoadError::default()), Ok(r#"theme = "workspace-theme""#.to_owned()))
+                .unwrap();
+
+        assert_eq!(config, Config::default());
+        assert_eq!(warnings.len(), 1);
+        assert!(matches!(
+            warnings[0],
+            ConfigLoadError::UntrustedWorkspaceConfig(WorkspaceConfigMode::Never)
+        ));
+    }
+
+    #[test]
+    fn prompt_mode_rejects_workspace_config_with_distinct_mode() {
+        let global = r#"load-workspace-config = "prompt""#;
+        let local = r#"theme = "workspace-theme""#;
+
+        let (config, warnings) =
+            Config::load(Ok(global.to_owned()), Ok(local.to_owned())).unwrap();
+
+        assert_eq!(config.theme, None);
+        assert_eq!(warnings.len(), 1);
+        assert!(matches!(
+            warnings[0],
+            ConfigLoadError::UntrustedWorkspaceConfig(WorkspaceConfigMode::Prompt)
+        ));
+    }
+
+    #[test]
+    fn trusted_workspace_config_strips_sensitive_keys_but_keeps_others() {
+        let global = r#"load-workspace-config = "always""#;
+        let local = r#"
+            theme = "workspace-theme"
+
+            [editor]
+            shell = ["sh", "-c"]
+            scrolloff = 10
+        "#;
+
+        let (config, warnings) =
+            Config::load(Ok(global.to_owned()), Ok(local.to_owned())).unwrap();
+
+        assert!(warnings.is_empty());
+        assert_eq!(config.theme.as_deref(), Some("workspace-theme"));
+        assert_eq!(
+            config.editor.shell,
+            helix_view::editor::Config::default().shell
+        );
+        assert_eq!(config.editor.scrolloff, 10);
+    }
+
+    #[test]
+    fn global_sensitive_editor_keys_extend_the_builtin_denylist() {
+        let global = r#"
+            load-workspace-config = "always"
+            sensitive-editor-keys = ["scrolloff"]
+        "#;
+        let local = r#"
+            [editor]
+            scrolloff = 10
+        "#;
+
+        let (config, warnings) =
+            Config::load(Ok(global.to_owned()), Ok(local.to_owned())).unwrap();
+
+        assert!(warnings.is_empty());
+        assert_eq!(
+            config.editor.scrolloff,
+            helix_view::editor::Config::default().scrolloff
+        );
+    }
+
+    #[test]
+    fn load_cascade_applies_trust_and_unbind_per_layer() {
+        use crate::keymap;
+        use helix_core::hashmap;
+
+        let global = r#"
+            load-workspace-config = "always"
+
+            [keys.normal]
+            g = "move_line_down"
+        "#;
+
+        // outermost workspace root: adds a binding on top of the global one
+        let root = r#"
+            theme = "root-theme"
+
+            [keys.normal]
+            h = "delete_selection"
+        "#;
+
+        // a nested project: wants a totally clean keymap, discarding both the
+        // built-in defaults and everything the outer layers contributed so far
+        let mid = r#"
+            [keys]
+            unbind-default-keys = true
+
+            [keys.normal]
+            j = "move_line_down"
+        "#;
+
+        // closest to the file being edited: layered on top of `mid`'s clean base
+        let leaf = r#"
+            [keys.normal]
+            k = "delete_selection"
+        "#;
+
+        let (config, warnings) = Config::load_cascade(
+            Ok(global.to_owned()),
+            vec![Ok(root.to_owned()), Ok(mid.to_owned()), Ok(leaf.to_owned())],
+        )
+        .unwrap();
+
+        assert!(warnings.is_empty());
+        assert_eq!(config.theme.as_deref(), Some("root-theme"));
+        assert_eq!(
+            config.keys.bindings,
+            hashmap! {
+                Mode::Normal => keymap!({ "Normal mode"
+                    "j" => move_line_down,
+                    "k" => delete_selection,
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn load_cascade_trust_gate_applies_to_every_layer() {
+        // global doesn't opt in, so *neither* local layer should ever be folded in,
+        // not just the first one encountered; folding stops at the first rejection
+        // rather than silently skipping it and trying the next layer.
+        let global = r#"load-workspace-config = "never""#;
+        let root = r#"theme = "root-theme""#;
+        let leaf = r#"theme = "leaf-theme""#;
+
+        let (config, warnings) = Config::load_cascade(
+            Ok(global.to_owned()),
+            vec![Ok(root.to_owned()), Ok(leaf.to_owned())],
+        )
+        .unwrap();
+
+        assert_eq!(config.theme, None);
+        assert_eq!(warnings.len(), 1);
+        assert!(matches!(
+            warnings[0],
+            ConfigLoadError::UntrustedWorkspaceConfig(WorkspaceConfigMode::Never)
+        ));
+    }
+
+    #[test]
+    fn unbind_default_keys_drops_the_builtin_keymap() {
+        use crate::keymap;
+        use helix_core::hashmap;
+
+        let config = Config::load_test(
+            r#"
+                [keys]
+                unbind-default-keys = true
+
+                [keys.normal]
+                g = "move_line_down"
+            "#,
+        );
+
+        // Not just "different from the defaults" -- nothing from `keymap::default()`
+        // should still be reachable, only what this single layer configured.
+        assert_eq!(
+            config.keys.bindings,
+            hashmap! {
+                Mode::Normal => keymap!({ "Normal mode"
+                    "g" => move_line_down,
+                }),
+            }
+        );
+        assert_ne!(config.keys.bindings, keymap::default());
+    }
+
+    #[test]
+    fn supertab_legacy_scalar_matches_explicit_non_whitespace_left_rule() {
+        let legacy: KeymapConfig = toml::from_str(r#"supertab = "move_line_down""#).unwrap();
+
+        let explicit: KeymapConfig = toml::from_str(
+            r#"
+                [[supertab]]
+                when = "non-whitespace-left"
+                command = "move_line_down"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(legacy.supertab, explicit.supertab);
+    }
+
+    #[test]
+    fn supertab_rule_list_preserves_declaration_order() {
+        let parsed: KeymapConfig = toml::from_str(
+            r#"
+                [[supertab]]
+                when = "completion-visible"
+                command = "move_line_down"
+
+                [[supertab]]
+                when = "in-snippet"
+                command = "delete_selection"
+
+                [[supertab]]
+                when = "always"
+                command = "move_line_down"
+            "#,
+        )
+        .unwrap();
+
+        let rules = parsed.supertab.unwrap();
+        assert_eq!(
+            rules.iter().map(|rule| rule.when).collect::<Vec<_>>(),
+            vec![
+                SuperTabCondition::CompletionVisible,
+                SuperTabCondition::InSnippet,
+                SuperTabCondition::Always,
+            ]
+        );
+    }
+
+    #[test]
+    fn local_supertab_rules_replace_global_wholesale() {
+        let global: ConfigRaw = toml::from_str(
+            r#"
+                [[keys.supertab]]
+                when = "always"
+                command = "move_line_down"
+            "#,
+        )
+        .unwrap();
+
+        let local: ConfigRaw = toml::from_str(
+            r#"
+                [[keys.supertab]]
+                when = "completion-visible"
+                command = "delete_selection"
+            "#,
+        )
+        .unwrap();
+
+        let merged = ConfigRaw::merge(global, local);
+        let rules = merged.keys.unwrap().supertab.unwrap();
+
+        // Only the local rule survives; the global one isn't concatenated alongside it.
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].when, SuperTabCondition::CompletionVisible);
+    }
+
+    #[test]
+    fn diff_reports_theme_icons_keys_and_editor_changes_independently() {
+        let base = Config::load_test("");
+
+        let theme_changed = Config::load_test(r#"theme = "dark""#);
+        let changes = base.diff(&theme_changed);
+        assert!(changes.theme);
+        assert!(!changes.icons);
+        assert!(!changes.keys);
+        assert!(changes.editor.is_empty());
+
+        let icons_changed = Config::load_test(r#"icons = "nerd""#);
+        let changes = base.diff(&icons_changed);
+        assert!(changes.icons);
+        assert!(!changes.theme);
+        assert!(!changes.keys);
+
+        let keys_changed = Config::load_test(
+            r#"
+                [keys.normal]
+                g = "move_line_down"
+            "#,
+        );
+        let changes = base.diff(&keys_changed);
+        assert!(changes.keys);
+        assert!(!changes.theme);
+        assert!(!changes.icons);
+
+        let editor_changed = Config::load_test(
+            r#"
+                [editor]
+                scrolloff = 10
+            "#,
+        );
+        let changes = base.diff(&editor_changed);
+        assert_eq!(changes.editor, vec!["scrolloff".to_string()]);
+        assert!(!changes.theme);
+        assert!(!changes.icons);
+        assert!(!changes.keys);
+    }
+
+    #[test]
+    fn config_changes_is_empty_reflects_whether_anything_differs() {
+        let base = Config::load_test("");
+        assert!(base.diff(&base).is_empty());
+
+        let changed = Config::load_test(r#"theme = "dark""#);
+        assert!(!base.diff(&changed).is_empty());
+    }
 }